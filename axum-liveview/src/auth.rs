@@ -0,0 +1,40 @@
+use axum::http::HeaderMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Per-connection context available to an [`AuthorizeMessage`] implementation. Captured once, at
+/// upgrade time, and shared by every message handled on that socket.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionContext {
+    pub(crate) headers: HeaderMap,
+}
+
+/// A hook invoked before the socket loop acts on an incoming message (subscribing to or
+/// broadcasting on a liveview's topics), so callers can enforce per-user access control or
+/// other policy without forking the socket loop itself.
+///
+/// Rejecting a message drops it the same way a malformed one is dropped: logged and ignored,
+/// the socket stays open.
+pub(crate) trait AuthorizeMessage: Send + Sync + 'static {
+    fn authorize(&self, ctx: &ConnectionContext, liveview_id: Uuid, topic: &str) -> bool;
+}
+
+impl<F> AuthorizeMessage for F
+where
+    F: Fn(&ConnectionContext, Uuid, &str) -> bool + Send + Sync + 'static,
+{
+    fn authorize(&self, ctx: &ConnectionContext, liveview_id: Uuid, topic: &str) -> bool {
+        self(ctx, liveview_id, topic)
+    }
+}
+
+/// The default policy: every message is allowed.
+pub(crate) struct AllowAll;
+
+impl AuthorizeMessage for AllowAll {
+    fn authorize(&self, _ctx: &ConnectionContext, _liveview_id: Uuid, _topic: &str) -> bool {
+        true
+    }
+}
+
+pub(crate) type SharedAuthorizeMessage = Arc<dyn AuthorizeMessage>;
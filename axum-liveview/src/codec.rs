@@ -0,0 +1,135 @@
+use axum::extract::ws;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// A decoded `(liveview_id, topic, data)` triple, independent of which [`Codec`] produced it.
+///
+/// Also doubles as the wire shape POSTed to `/live/event`, so that endpoint decodes client
+/// events the exact same way the WebSocket loop does.
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawMessage {
+    pub(crate) liveview_id: Uuid,
+    pub(crate) topic: String,
+    pub(crate) data: Value,
+}
+
+/// How messages are serialized between the server and the client over the `/live` socket.
+///
+/// Swapping the codec doesn't change anything about the protocol itself, just the bytes it's
+/// packed into, so `JsonCodec` and `MsgPackCodec` share the exact same `[liveview_id, topic,
+/// data]` shape.
+pub(crate) trait Codec: Clone + Send + Sync + 'static {
+    /// The name used to negotiate this codec, e.g. as a `?codec=` query param value or a
+    /// websocket subprotocol.
+    const NAME: &'static str;
+
+    fn encode<T>(&self, liveview_id: Uuid, topic: &'static str, msg: &T) -> ws::Message
+    where
+        T: Serialize;
+
+    fn decode(&self, msg: ws::Message) -> anyhow::Result<RawMessage>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct JsonCodec;
+
+impl Codec for JsonCodec {
+    const NAME: &'static str = "json";
+
+    fn encode<T>(&self, liveview_id: Uuid, topic: &'static str, msg: &T) -> ws::Message
+    where
+        T: Serialize,
+    {
+        let msg = serde_json::json!([liveview_id, topic, msg]);
+        ws::Message::Text(serde_json::to_string(&msg).unwrap())
+    }
+
+    fn decode(&self, msg: ws::Message) -> anyhow::Result<RawMessage> {
+        let text = match msg {
+            ws::Message::Text(text) => text,
+            other => anyhow::bail!("expected a text frame for `JsonCodec`, got {:?}", other),
+        };
+
+        let (liveview_id, topic, data): (Uuid, String, Value) = serde_json::from_str(&text)?;
+        Ok(RawMessage {
+            liveview_id,
+            topic,
+            data,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MsgPackCodec;
+
+impl Codec for MsgPackCodec {
+    const NAME: &'static str = "msgpack";
+
+    fn encode<T>(&self, liveview_id: Uuid, topic: &'static str, msg: &T) -> ws::Message
+    where
+        T: Serialize,
+    {
+        let msg = serde_json::json!([liveview_id, topic, msg]);
+        let bytes = rmp_serde::to_vec(&msg).expect("failed to encode message as MessagePack");
+        ws::Message::Binary(bytes)
+    }
+
+    fn decode(&self, msg: ws::Message) -> anyhow::Result<RawMessage> {
+        let bytes = match msg {
+            ws::Message::Binary(bytes) => bytes,
+            other => anyhow::bail!(
+                "expected a binary frame for `MsgPackCodec`, got {:?}",
+                other
+            ),
+        };
+
+        let (liveview_id, topic, data): (Uuid, String, Value) = rmp_serde::from_slice(&bytes)?;
+        Ok(RawMessage {
+            liveview_id,
+            topic,
+            data,
+        })
+    }
+}
+
+/// An erased codec, chosen per-connection at upgrade time based on the negotiated subprotocol
+/// or `?codec=` query param.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum AnyCodec {
+    Json(JsonCodec),
+    MsgPack(MsgPackCodec),
+}
+
+impl Default for AnyCodec {
+    fn default() -> Self {
+        Self::Json(JsonCodec)
+    }
+}
+
+impl AnyCodec {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            JsonCodec::NAME => Some(Self::Json(JsonCodec)),
+            MsgPackCodec::NAME => Some(Self::MsgPack(MsgPackCodec)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn encode<T>(&self, liveview_id: Uuid, topic: &'static str, msg: &T) -> ws::Message
+    where
+        T: Serialize,
+    {
+        match self {
+            Self::Json(codec) => codec.encode(liveview_id, topic, msg),
+            Self::MsgPack(codec) => codec.encode(liveview_id, topic, msg),
+        }
+    }
+
+    pub(crate) fn decode(&self, msg: ws::Message) -> anyhow::Result<RawMessage> {
+        match self {
+            Self::Json(codec) => codec.decode(msg),
+            Self::MsgPack(codec) => codec.decode(msg),
+        }
+    }
+}
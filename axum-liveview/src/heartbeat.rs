@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+/// How often the server pings an open `/live` socket, and how long it'll wait without hearing
+/// anything back before treating the connection as dead.
+///
+/// A half-open TCP connection (client gone, no FIN) otherwise leaks the socket task and its
+/// pubsub subscriptions forever, since `WebSocket::recv` never resolves and nothing else in the
+/// select loop wakes up to notice.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HeartbeatConfig {
+    pub(crate) ping_interval: Duration,
+    pub(crate) idle_timeout: Duration,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(15),
+            idle_timeout: Duration::from_secs(45),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// A per-binding rate-limit hint, sent alongside high-frequency events like `axum/live-keydown` or
+/// `axum/live-input` so the client doesn't flood the server (and every other subscriber) with a
+/// broadcast per keystroke.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum RateLimit {
+    /// Only broadcast once no new event has arrived for `ms` milliseconds.
+    Debounce { ms: u64 },
+    /// Broadcast at most once every `ms` milliseconds, dropping events in between.
+    Throttle { ms: u64 },
+}
+
+/// What the caller should do with an incoming event after consulting the rate limiter.
+pub(crate) enum Decision {
+    /// Broadcast it right now.
+    Now,
+    /// Drop it; a throttle window is still open.
+    Drop,
+    /// Wait `after` before broadcasting, and only go through with it if `token` still matches
+    /// the latest scheduled broadcast for this binding (i.e. no newer event has superseded it).
+    Debounced {
+        after: Duration,
+        token: DebounceToken,
+    },
+}
+
+/// A handle a deferred broadcast uses to check whether it's still the most recent one scheduled
+/// for its `(liveview_id, event_name)` binding.
+#[derive(Clone)]
+pub(crate) struct DebounceToken {
+    generation: Arc<AtomicU64>,
+    expected: u64,
+}
+
+impl DebounceToken {
+    /// Returns `true` if no later event has rescheduled this binding's debounce in the meantime.
+    pub(crate) fn is_current(&self) -> bool {
+        self.generation.load(Ordering::SeqCst) == self.expected
+    }
+}
+
+#[derive(Default)]
+struct BindingState {
+    last_broadcast: Option<Instant>,
+    generation: Arc<AtomicU64>,
+}
+
+/// Tracks throttle/debounce state per `(liveview_id, event_name)` binding for the lifetime of a
+/// single socket.
+#[derive(Default)]
+pub(crate) struct RateLimiter {
+    bindings: HashMap<(Uuid, String), BindingState>,
+}
+
+impl RateLimiter {
+    pub(crate) fn check(
+        &mut self,
+        liveview_id: Uuid,
+        event_name: &str,
+        hint: Option<RateLimit>,
+    ) -> Decision {
+        let Some(hint) = hint else {
+            return Decision::Now;
+        };
+
+        let binding = self
+            .bindings
+            .entry((liveview_id, event_name.to_owned()))
+            .or_default();
+
+        match hint {
+            RateLimit::Throttle { ms } => {
+                let now = Instant::now();
+                if let Some(last) = binding.last_broadcast {
+                    if now.duration_since(last) < Duration::from_millis(ms) {
+                        return Decision::Drop;
+                    }
+                }
+                binding.last_broadcast = Some(now);
+                Decision::Now
+            }
+            RateLimit::Debounce { ms } => {
+                let generation = binding.generation.fetch_add(1, Ordering::SeqCst) + 1;
+                Decision::Debounced {
+                    after: Duration::from_millis(ms),
+                    token: DebounceToken {
+                        generation: Arc::clone(&binding.generation),
+                        expected: generation,
+                    },
+                }
+            }
+        }
+    }
+}
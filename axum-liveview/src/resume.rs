@@ -0,0 +1,164 @@
+use crate::{html::Diff, liveview::liveview_local_topic, pubsub::PubSub, PubSubExt};
+use axum::Json;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+/// The topic diffs are assigned a version on and republished to. The raw `"rendered"` topic
+/// (still `Json<Diff>`, unversioned) keeps working for anything that doesn't care about resume;
+/// this is a second, derived feed so existing publishers don't need to know about versioning.
+pub(crate) const RENDERED_VERSIONED_TOPIC: &str = "rendered-versioned";
+
+/// A [`Diff`] tagged with a version number that's monotonically increasing per `liveview_id`.
+/// Every subscriber to a liveview's [`RENDERED_VERSIONED_TOPIC`] sees the same numbering, since
+/// the version is assigned once, by the single relay task each liveview gets, not per-subscriber.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct VersionedDiff {
+    pub(crate) version: u64,
+    pub(crate) diff: Diff,
+}
+
+struct RingBuffer {
+    next_version: u64,
+    diffs: VecDeque<VersionedDiff>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            next_version: 0,
+            diffs: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, diff: Diff) -> VersionedDiff {
+        let versioned = VersionedDiff {
+            version: self.next_version,
+            diff,
+        };
+        self.next_version += 1;
+
+        self.diffs.push_back(versioned.clone());
+        if self.diffs.len() > self.capacity {
+            self.diffs.pop_front();
+        }
+
+        versioned
+    }
+
+    /// Diffs with a version greater than `last_seen_version`, or `None` if some of them have
+    /// already fallen out of the buffer (or `last_seen_version` isn't one this buffer ever
+    /// produced, e.g. after a process restart reset numbering back to 0) and a full render is
+    /// needed instead.
+    fn since(&self, last_seen_version: u64) -> Option<Vec<VersionedDiff>> {
+        if last_seen_version >= self.next_version {
+            return None;
+        }
+
+        match self.diffs.front() {
+            Some(oldest) if oldest.version <= last_seen_version + 1 => Some(
+                self.diffs
+                    .iter()
+                    .filter(|versioned| versioned.version > last_seen_version)
+                    .cloned()
+                    .collect(),
+            ),
+            // Either the oldest kept diff is too new, or nothing is kept at all (capacity 0)
+            // even though diffs were produced: the missed ones are gone either way.
+            _ => None,
+        }
+    }
+}
+
+/// The per-process registry of diff ring buffers, one per mounted liveview, plus bookkeeping for
+/// the relay task that assigns each buffer's versions. Shared by every socket/SSE handler that
+/// needs to replay diffs on reconnect.
+#[derive(Clone)]
+pub(crate) struct DiffBuffers {
+    capacity: usize,
+    buffers: Arc<Mutex<HashMap<Uuid, RingBuffer>>>,
+    relays_running: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+impl DiffBuffers {
+    /// `capacity` is how many diffs are kept around per liveview. Past this many diffs the
+    /// oldest are dropped, at which point a client that's fallen further behind than this has to
+    /// fall back to a full initial render.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            buffers: Arc::default(),
+            relays_running: Arc::default(),
+        }
+    }
+
+    /// Ensures a single version-assigning relay is running for `liveview_id`: it subscribes to
+    /// the liveview's raw `"rendered"` diffs, assigns each the next version and stores it in the
+    /// ring buffer, then republishes it on [`RENDERED_VERSIONED_TOPIC`] for mount/resume
+    /// subscribers. Calling this more than once for the same liveview is a no-op; the relay
+    /// exits (and tears down its buffer) once the raw `"rendered"` topic closes, i.e. once the
+    /// liveview itself is gone.
+    pub(crate) fn ensure_relay<P>(&self, liveview_id: Uuid, pubsub: P)
+    where
+        P: PubSub + Clone + Send + Sync + 'static,
+    {
+        if !self.relays_running.lock().unwrap().insert(liveview_id) {
+            return;
+        }
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut diffs = pubsub
+                .subscribe::<Json<Diff>>(&liveview_local_topic(liveview_id, "rendered"))
+                .await;
+
+            while let Some(Json(diff)) = diffs.next().await {
+                let versioned = this.record(liveview_id, diff);
+                let _ = pubsub
+                    .broadcast(
+                        &liveview_local_topic(liveview_id, RENDERED_VERSIONED_TOPIC),
+                        Json(versioned),
+                    )
+                    .await;
+            }
+
+            this.remove(liveview_id);
+        });
+    }
+
+    /// Assigns the next version for `liveview_id` and stores the resulting diff in its buffer.
+    fn record(&self, liveview_id: Uuid, diff: Diff) -> VersionedDiff {
+        let mut buffers = self.buffers.lock().unwrap();
+        buffers
+            .entry(liveview_id)
+            .or_insert_with(|| RingBuffer::new(self.capacity))
+            .push(diff)
+    }
+
+    /// Diffs published after `last_seen_version`, or `None` if the buffer no longer has enough
+    /// history to resume from (either it fell out of the window, or the liveview was never
+    /// mounted in this process).
+    pub(crate) fn since(
+        &self,
+        liveview_id: Uuid,
+        last_seen_version: u64,
+    ) -> Option<Vec<VersionedDiff>> {
+        self.buffers
+            .lock()
+            .unwrap()
+            .get(&liveview_id)?
+            .since(last_seen_version)
+    }
+
+    /// Drops the buffer for a liveview that's no longer mounted anywhere.
+    fn remove(&self, liveview_id: Uuid) {
+        self.buffers.lock().unwrap().remove(&liveview_id);
+        self.relays_running.lock().unwrap().remove(&liveview_id);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    auth::ConnectionContext,
+    codec::RawMessage,
+    html,
+    liveview::liveview_local_topic,
+    pubsub::PubSub,
+    resume::{VersionedDiff, RENDERED_VERSIONED_TOPIC},
+    ws::{broadcast_message, Message},
+    LiveViewManager, PubSubExt,
+};
+use axum::{
+    extract::Query,
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use futures_util::{stream::BoxStream, Stream, StreamExt};
+use serde::Deserialize;
+use std::{convert::Infallible, time::Duration};
+use uuid::Uuid;
+
+/// How long `sse()` waits for a mount's initial render before giving up and returning 404.
+///
+/// Nothing closes the `initial-render` subscription for a `liveview_id` that's never mounted
+/// (there's no producer to notice and hang up), so without a bound the request would otherwise
+/// wait forever.
+const MOUNT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) fn routes<B>() -> Router<B>
+where
+    B: Send + 'static,
+{
+    Router::new()
+        .route("/live/sse", get(sse))
+        .route("/live/event", post(event))
+}
+
+#[derive(Debug, Deserialize)]
+struct SseQuery {
+    liveview_id: Uuid,
+}
+
+/// A one-way fallback transport for environments that drop WebSocket upgrades. The client
+/// receives the initial render and subsequent diffs as SSE events, and posts its own events to
+/// the companion `/live/event` route.
+async fn sse(Query(SseQuery { liveview_id }): Query<SseQuery>, live: LiveViewManager) -> Response {
+    let pubsub = live.pubsub;
+
+    let mut initial_render_stream = pubsub
+        .subscribe::<Json<html::Serialized>>(&liveview_local_topic(liveview_id, "initial-render"))
+        .await;
+
+    let _ = pubsub
+        .broadcast(&liveview_local_topic(liveview_id, "mounted"), ())
+        .await;
+
+    let initial_render = match tokio::time::timeout(MOUNT_TIMEOUT, initial_render_stream.next())
+        .await
+    {
+        Ok(Some(Json(initial_render))) => initial_render,
+        Ok(None) => {
+            tracing::warn!(
+                %liveview_id,
+                "initial-render topic closed before a render was published; liveview was never mounted"
+            );
+            return (StatusCode::NOT_FOUND, "liveview not found").into_response();
+        }
+        Err(_) => {
+            tracing::warn!(%liveview_id, "timed out waiting for initial render; liveview_id was never mounted");
+            return (StatusCode::NOT_FOUND, "liveview not found").into_response();
+        }
+    };
+
+    live.diff_buffers.ensure_relay(liveview_id, pubsub.clone());
+
+    let diff_stream = pubsub
+        .subscribe::<Json<VersionedDiff>>(&liveview_local_topic(
+            liveview_id,
+            RENDERED_VERSIONED_TOPIC,
+        ))
+        .await
+        .map(|Json(diff)| diff);
+
+    Sse::new(sse_stream(initial_render, diff_stream))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn sse_stream(
+    initial_render: html::Serialized,
+    diff_stream: BoxStream<'static, VersionedDiff>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let initial_render = futures_util::stream::once(async move {
+        Ok(Event::default()
+            .event("initial-render")
+            .json_data(initial_render)
+            .expect("`html::Serialized` should always serialize to JSON"))
+    });
+
+    let diffs = diff_stream.map(|diff| {
+        Ok(Event::default()
+            .event("rendered")
+            .json_data(diff)
+            .expect("`VersionedDiff` should always serialize to JSON"))
+    });
+
+    initial_render.chain(diffs)
+}
+
+/// The request-originated counterpart to `sse`: since SSE is one-way, the client POSTs its
+/// click/input events here instead of sending them down the socket. The body is the same
+/// `(liveview_id, topic, data)` shape the WebSocket wire protocol uses, decoded and authorized
+/// the exact same way `handle_message_from_socket` does, so both transports agree on what's
+/// allowed and what gets broadcast where.
+async fn event(
+    live: LiveViewManager,
+    headers: HeaderMap,
+    Json(msg): Json<RawMessage>,
+) -> impl IntoResponse {
+    let liveview_id = msg.liveview_id;
+    let ctx = ConnectionContext { headers };
+
+    if !live.authorize.authorize(&ctx, liveview_id, &msg.topic) {
+        tracing::warn!(%liveview_id, topic = %msg.topic, "message rejected by AuthorizeMessage");
+        return;
+    }
+
+    let msg = match Message::try_from(msg) {
+        Ok(msg) => msg,
+        Err(err) => {
+            tracing::error!(%err, "failed to decode /live/event payload");
+            return;
+        }
+    };
+
+    broadcast_message(&live.pubsub, liveview_id, msg).await;
+}
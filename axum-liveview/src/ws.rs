@@ -1,18 +1,28 @@
 use crate::{
-    html::{self, Diff},
+    auth::{ConnectionContext, SharedAuthorizeMessage},
+    codec::{AnyCodec, Codec, JsonCodec, MsgPackCodec, RawMessage},
+    heartbeat::HeartbeatConfig,
+    html,
     liveview::liveview_local_topic,
     pubsub::PubSub,
+    rate_limit::{Decision, RateLimit, RateLimiter},
+    resume::{DiffBuffers, VersionedDiff, RENDERED_VERSIONED_TOPIC},
     LiveViewManager, PubSubExt,
 };
 use axum::{
-    extract::ws::{self, WebSocket, WebSocketUpgrade},
+    extract::{
+        ws::{self, WebSocket, WebSocketUpgrade},
+        Query,
+    },
+    http::HeaderMap,
     response::IntoResponse,
     routing::get,
     Json, Router,
 };
 use futures_util::{stream::BoxStream, StreamExt};
 use serde::Deserialize;
-use serde_json::{from_value, json, Value};
+use serde_json::{from_value, Value};
+use std::collections::HashMap;
 use tokio_stream::StreamMap;
 use uuid::Uuid;
 
@@ -23,30 +33,132 @@ where
     Router::new().route("/live", get(ws))
 }
 
-async fn ws(upgrade: WebSocketUpgrade, live: LiveViewManager) -> impl IntoResponse {
-    upgrade.on_upgrade(move |socket| handle_socket(socket, live.pubsub))
+#[derive(Debug, Deserialize)]
+struct WsQuery {
+    codec: Option<String>,
+}
+
+async fn ws(
+    upgrade: WebSocketUpgrade,
+    Query(query): Query<WsQuery>,
+    headers: HeaderMap,
+    live: LiveViewManager,
+) -> impl IntoResponse {
+    // The `Sec-WebSocket-Protocol` subprotocol takes priority over the query param since it's
+    // negotiated as part of the handshake itself; the query param exists for clients that can't
+    // set a subprotocol (e.g. the browser's native `WebSocket` constructor can, but curl can't).
+    let requested_protocol = headers
+        .get("sec-websocket-protocol")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .find_map(AnyCodec::from_name)
+        });
+
+    let codec = requested_protocol
+        .or_else(|| query.codec.as_deref().and_then(AnyCodec::from_name))
+        .unwrap_or_default();
+
+    let protocol_name = match codec {
+        AnyCodec::Json(_) => JsonCodec::NAME,
+        AnyCodec::MsgPack(_) => MsgPackCodec::NAME,
+    };
+
+    let ctx = ConnectionContext { headers };
+
+    upgrade
+        .protocols([protocol_name])
+        .on_upgrade(move |socket| {
+            handle_socket(
+                socket,
+                live.pubsub,
+                live.diff_buffers,
+                live.authorize,
+                live.max_mounts,
+                live.heartbeat,
+                ctx,
+                codec,
+            )
+        })
 }
 
 #[derive(Default)]
 struct SocketState {
-    diff_streams: StreamMap<Uuid, BoxStream<'static, Diff>>,
+    diff_streams: StreamMap<Uuid, BoxStream<'static, VersionedDiff>>,
+    codec: AnyCodec,
+    rate_limiter: RateLimiter,
+    /// At most one in-flight debounced broadcast per `(liveview_id, event_name)` binding.
+    /// Scheduling a new one aborts and replaces whatever was already pending for that binding,
+    /// so a long-lived socket with a repeatedly-debounced binding can't accumulate a handle per
+    /// event; the rest are cleaned up on disconnect.
+    debounce_tasks: HashMap<(Uuid, String), tokio::task::JoinHandle<()>>,
 }
 
-async fn handle_socket<P>(mut socket: WebSocket, pubsub: P)
-where
-    P: PubSub,
+async fn handle_socket<P>(
+    mut socket: WebSocket,
+    pubsub: P,
+    diff_buffers: DiffBuffers,
+    authorize: SharedAuthorizeMessage,
+    max_mounts: usize,
+    heartbeat: HeartbeatConfig,
+    ctx: ConnectionContext,
+    codec: AnyCodec,
+) where
+    P: PubSub + Clone + Send + Sync + 'static,
 {
-    let mut state = SocketState::default();
+    let mut state = SocketState {
+        codec,
+        ..Default::default()
+    };
+
+    let mut last_seen = tokio::time::Instant::now();
+    let mut ping_interval = tokio::time::interval(heartbeat.ping_interval);
+    ping_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
     loop {
         tokio::select! {
             Some(msg) = socket.recv() => {
                 match msg {
+                    Ok(ws::Message::Ping(_) | ws::Message::Pong(_)) => {
+                        // Liveness only; neither is a protocol message for `handle_message_from_socket`
+                        // to decode. Axum answers inbound `Ping`s with a `Pong` automatically, so in
+                        // practice this arm only ever sees the `Pong`s replying to our own pings below.
+                        last_seen = tokio::time::Instant::now();
+                    }
                     Ok(msg) => {
-                        if let Some((liveview_id, html)) = handle_message_from_socket(msg, &pubsub, &mut state).await {
-                            if send_message_to_socket(&mut socket, liveview_id, INITIAL_RENDER_TOPIC, html).await.is_err() {
-                                break;
+                        last_seen = tokio::time::Instant::now();
+
+                        match handle_message_from_socket(
+                            msg,
+                            &pubsub,
+                            &diff_buffers,
+                            &authorize,
+                            &ctx,
+                            max_mounts,
+                            &mut state,
+                        )
+                        .await
+                        {
+                            Some(MountOutcome::FullRender { liveview_id, html }) => {
+                                if send_message_to_socket(&mut socket, liveview_id, INITIAL_RENDER_TOPIC, html, state.codec).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Some(MountOutcome::Resumed { liveview_id, diffs }) => {
+                                let mut error = false;
+                                for diff in diffs {
+                                    if send_message_to_socket(&mut socket, liveview_id, RENDERED_TOPIC, diff, state.codec).await.is_err() {
+                                        error = true;
+                                        break;
+                                    }
+                                }
+                                if error {
+                                    break;
+                                }
                             }
+                            None => {}
                         }
                     }
                     Err(err) => {
@@ -57,13 +169,28 @@ where
             }
 
             Some((liveview_id, diff)) = state.diff_streams.next() => {
-                if send_message_to_socket(&mut socket, liveview_id, RENDERED_TOPIC, diff).await.is_err() {
+                if send_message_to_socket(&mut socket, liveview_id, RENDERED_TOPIC, diff, state.codec).await.is_err() {
+                    break;
+                }
+            }
+
+            _ = ping_interval.tick() => {
+                if last_seen.elapsed() > heartbeat.idle_timeout {
+                    tracing::debug!(?heartbeat, "closing idle socket");
+                    break;
+                }
+
+                if socket.send(ws::Message::Ping(Vec::new())).await.is_err() {
                     break;
                 }
             }
         }
     }
 
+    for (_, handle) in state.debounce_tasks.drain() {
+        handle.abort();
+    }
+
     let liveview_ids = state
         .diff_streams
         .iter()
@@ -88,76 +215,234 @@ async fn send_message_to_socket<T>(
     liveview_id: Uuid,
     topic: &'static str,
     msg: T,
+    codec: AnyCodec,
 ) -> Result<(), axum::Error>
 where
     T: serde::Serialize,
 {
-    let msg = json!([liveview_id, topic, msg,]);
-    let msg = serde_json::to_string(&msg).unwrap();
-    tracing::trace!(%msg, "sending message to websocket");
+    let msg = codec.encode(liveview_id, topic, &msg);
+    tracing::trace!(?msg, "sending message to websocket");
 
-    socket.send(ws::Message::Text(msg)).await
+    socket.send(msg).await
 }
 
-async fn handle_message_from_socket<P>(
-    msg: ws::Message,
-    pubsub: &P,
-    state: &mut SocketState,
-) -> Option<(Uuid, html::Serialized)>
-where
-    P: PubSub,
-{
-    #[derive(Debug, Deserialize)]
-    struct RawMessage {
+/// What a `Message::Mount` resolves to: either this is the client's first time mounting (or it
+/// fell too far out of the resume window) and it needs the full render, or it's reconnecting
+/// within the resume window and just needs the diffs it missed.
+enum MountOutcome {
+    FullRender {
         liveview_id: Uuid,
-        topic: String,
-        data: Value,
-    }
+        html: html::Serialized,
+    },
+    Resumed {
+        liveview_id: Uuid,
+        diffs: Vec<VersionedDiff>,
+    },
+}
 
-    impl TryFrom<RawMessage> for Message {
-        type Error = anyhow::Error;
+/// A [`RawMessage`] decoded into its typed, topic-specific shape. Shared between the WebSocket
+/// loop and the `/live/event` SSE-fallback endpoint so both transports agree on what a given wire
+/// topic means and, in particular, authorize against the same topic string.
+#[derive(Debug)]
+pub(crate) enum Message {
+    Mount(MountPayload),
+    LiveClick(LiveClick),
+    LiveInput(LiveInput),
+    LiveSubmit(LiveSubmit),
+    LiveKey(KeyPhase, LiveKey),
+    LiveFocus(LiveFocusOrBlur),
+    LiveBlur(LiveFocusOrBlur),
+    LiveChange(LiveInput),
+}
 
-        fn try_from(value: RawMessage) -> Result<Self, Self::Error> {
-            let RawMessage {
-                topic,
-                data,
-                liveview_id: _,
-            } = value;
+impl TryFrom<RawMessage> for Message {
+    type Error = anyhow::Error;
 
-            match &*topic {
-                "axum/mount-liveview" => Ok(Message::Mount),
-                "axum/live-click" => Ok(Message::LiveClick(from_value(data)?)),
-                "axum/live-input" => Ok(Message::LiveInput(from_value(data)?)),
-                other => {
-                    anyhow::bail!("unknown message topic: {:?}", other)
-                }
+    fn try_from(value: RawMessage) -> Result<Self, Self::Error> {
+        let RawMessage {
+            topic,
+            data,
+            liveview_id: _,
+        } = value;
+
+        match &*topic {
+            "axum/mount-liveview" => {
+                let payload = if data.is_null() {
+                    MountPayload::default()
+                } else {
+                    from_value(data)?
+                };
+                Ok(Message::Mount(payload))
+            }
+            "axum/live-click" => Ok(Message::LiveClick(from_value(data)?)),
+            "axum/live-input" => Ok(Message::LiveInput(from_value(data)?)),
+            "axum/live-submit" => Ok(Message::LiveSubmit(from_value(data)?)),
+            "axum/live-keydown" => Ok(Message::LiveKey(KeyPhase::Down, from_value(data)?)),
+            "axum/live-keyup" => Ok(Message::LiveKey(KeyPhase::Up, from_value(data)?)),
+            "axum/live-focus" => Ok(Message::LiveFocus(from_value(data)?)),
+            "axum/live-blur" => Ok(Message::LiveBlur(from_value(data)?)),
+            "axum/live-change" => Ok(Message::LiveChange(from_value(data)?)),
+            other => {
+                anyhow::bail!("unknown message topic: {:?}", other)
             }
         }
     }
+}
 
-    #[derive(Debug)]
-    enum Message {
-        Mount,
-        LiveClick(LiveClick),
-        LiveInput(LiveInput),
-    }
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct MountPayload {
+    /// The version of the last diff the client applied before it disconnected. If it's
+    /// still within the liveview's resume window, the server replays just the diffs since
+    /// then instead of sending a full initial render.
+    #[serde(rename = "lv")]
+    last_seen_version: Option<u64>,
+}
 
-    #[derive(Debug, Deserialize)]
-    struct LiveClick {
-        #[serde(rename = "e")]
-        event_name: String,
-        #[serde(rename = "d")]
-        additional_data: Option<Value>,
-    }
+#[derive(Debug, Deserialize)]
+pub(crate) struct LiveClick {
+    #[serde(rename = "e")]
+    event_name: String,
+    #[serde(rename = "d")]
+    additional_data: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LiveInput {
+    #[serde(rename = "e")]
+    event_name: String,
+    #[serde(rename = "v")]
+    value: String,
+    #[serde(rename = "rl")]
+    rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LiveSubmit {
+    #[serde(rename = "e")]
+    event_name: String,
+    #[serde(rename = "f")]
+    form: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LiveKey {
+    #[serde(rename = "e")]
+    event_name: String,
+    #[serde(rename = "k")]
+    key: String,
+    #[serde(rename = "c")]
+    code: String,
+    #[serde(rename = "alt", default)]
+    alt: bool,
+    #[serde(rename = "ctrl", default)]
+    ctrl: bool,
+    #[serde(rename = "shift", default)]
+    shift: bool,
+    #[serde(rename = "meta", default)]
+    meta: bool,
+    #[serde(rename = "rl")]
+    rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LiveFocusOrBlur {
+    #[serde(rename = "e")]
+    event_name: String,
+}
 
-    #[derive(Debug, Deserialize)]
-    struct LiveInput {
-        #[serde(rename = "e")]
-        event_name: String,
-        #[serde(rename = "v")]
-        value: String,
+/// Broadcasts a decoded [`Message`] the same way [`handle_message_from_socket`] would, minus
+/// rate limiting and mounting: used by the stateless `/live/event` endpoint, which has no
+/// per-socket [`SocketState`] to debounce/throttle against and no diff stream to mount onto.
+pub(crate) async fn broadcast_message<P>(pubsub: &P, liveview_id: Uuid, msg: Message)
+where
+    P: PubSub + Clone,
+{
+    match msg {
+        Message::Mount(_) => {
+            tracing::warn!(
+                %liveview_id,
+                "ignoring axum/mount-liveview on the broadcast-only /live/event transport"
+            );
+        }
+        Message::LiveClick(LiveClick {
+            event_name,
+            additional_data,
+        }) => {
+            let topic = liveview_local_topic(liveview_id, &event_name);
+            let result = match additional_data {
+                Some(additional_data) => pubsub.broadcast(&topic, Json(additional_data)).await,
+                None => pubsub.broadcast(&topic, ()).await,
+            };
+            if let Err(err) = result {
+                tracing::error!(%err, "failed to broadcast event");
+            }
+        }
+        Message::LiveInput(LiveInput {
+            event_name, value, ..
+        })
+        | Message::LiveChange(LiveInput {
+            event_name, value, ..
+        }) => {
+            let topic = liveview_local_topic(liveview_id, &event_name);
+            if let Err(err) = pubsub.broadcast(&topic, value).await {
+                tracing::error!(%err, "failed to broadcast event");
+            }
+        }
+        Message::LiveSubmit(LiveSubmit { event_name, form }) => {
+            let topic = liveview_local_topic(liveview_id, &event_name);
+            if let Err(err) = pubsub.broadcast(&topic, form).await {
+                tracing::error!(%err, "failed to broadcast event");
+            }
+        }
+        Message::LiveKey(
+            phase,
+            LiveKey {
+                event_name,
+                key,
+                code,
+                alt,
+                ctrl,
+                shift,
+                meta,
+                ..
+            },
+        ) => {
+            let topic = liveview_local_topic(liveview_id, &event_name);
+            let payload = LiveKeyPayload {
+                phase,
+                key,
+                code,
+                alt,
+                ctrl,
+                shift,
+                meta,
+            };
+            if let Err(err) = pubsub.broadcast(&topic, payload).await {
+                tracing::error!(%err, "failed to broadcast event");
+            }
+        }
+        Message::LiveFocus(LiveFocusOrBlur { event_name })
+        | Message::LiveBlur(LiveFocusOrBlur { event_name }) => {
+            let topic = liveview_local_topic(liveview_id, &event_name);
+            if let Err(err) = pubsub.broadcast(&topic, ()).await {
+                tracing::error!(%err, "failed to broadcast event");
+            }
+        }
     }
+}
 
+async fn handle_message_from_socket<P>(
+    msg: ws::Message,
+    pubsub: &P,
+    diff_buffers: &DiffBuffers,
+    authorize: &SharedAuthorizeMessage,
+    ctx: &ConnectionContext,
+    max_mounts: usize,
+    state: &mut SocketState,
+) -> Option<MountOutcome>
+where
+    P: PubSub + Clone + Send + Sync + 'static,
+{
     macro_rules! try_ {
         ($expr:expr, $pattern:path $(,)?) => {
             match $expr {
@@ -170,33 +455,65 @@ where
         };
     }
 
-    let text = try_!(msg, ws::Message::Text);
-    let msg: RawMessage = try_!(serde_json::from_str(&text), Ok);
+    let msg: RawMessage = try_!(state.codec.decode(msg), Ok);
     let liveview_id = msg.liveview_id;
+
+    if !authorize.authorize(ctx, liveview_id, &msg.topic) {
+        tracing::warn!(%liveview_id, topic = %msg.topic, "message rejected by AuthorizeMessage");
+        return None;
+    }
+
     let msg = try_!(Message::try_from(msg), Ok);
 
     tracing::trace!(?msg, "received message from websocket");
 
     match msg {
-        Message::Mount => {
-            let mut initial_render_stream = pubsub
-                .subscribe::<Json<html::Serialized>>(&liveview_local_topic(
+        Message::Mount(MountPayload { last_seen_version }) => {
+            if !state.diff_streams.contains_key(&liveview_id)
+                && state.diff_streams.len() >= max_mounts
+            {
+                tracing::warn!(
+                    %liveview_id,
+                    max_mounts,
+                    "refusing to mount: socket is already at its mount limit"
+                );
+                return None;
+            }
+
+            let resumed = last_seen_version.and_then(|v| diff_buffers.since(liveview_id, v));
+
+            let outcome = if let Some(missed_diffs) = resumed {
+                Some(MountOutcome::Resumed {
                     liveview_id,
-                    "initial-render",
-                ))
-                .await;
+                    diffs: missed_diffs,
+                })
+            } else {
+                let mut initial_render_stream = pubsub
+                    .subscribe::<Json<html::Serialized>>(&liveview_local_topic(
+                        liveview_id,
+                        "initial-render",
+                    ))
+                    .await;
 
-            try_!(
-                pubsub
-                    .broadcast(&liveview_local_topic(liveview_id, "mounted"), ())
-                    .await,
-                Ok,
-            );
+                try_!(
+                    pubsub
+                        .broadcast(&liveview_local_topic(liveview_id, "mounted"), ())
+                        .await,
+                    Ok,
+                );
+
+                let Json(html) = try_!(initial_render_stream.next().await, Some);
 
-            let Json(msg) = try_!(initial_render_stream.next().await, Some);
+                Some(MountOutcome::FullRender { liveview_id, html })
+            };
+
+            diff_buffers.ensure_relay(liveview_id, pubsub.clone());
 
             let diff_stream = pubsub
-                .subscribe::<Json<Diff>>(&liveview_local_topic(liveview_id, "rendered"))
+                .subscribe::<Json<VersionedDiff>>(&liveview_local_topic(
+                    liveview_id,
+                    RENDERED_VERSIONED_TOPIC,
+                ))
                 .await
                 .map(|Json(diff)| diff);
 
@@ -204,7 +521,7 @@ where
                 .diff_streams
                 .insert(liveview_id, Box::pin(diff_stream));
 
-            Some((liveview_id, msg))
+            outcome
         }
         Message::LiveClick(LiveClick {
             event_name,
@@ -222,11 +539,146 @@ where
 
             None
         }
-        Message::LiveInput(LiveInput { event_name, value }) => {
+        Message::LiveInput(LiveInput {
+            event_name,
+            value,
+            rate_limit,
+        }) => {
+            broadcast_rate_limited(pubsub, state, liveview_id, &event_name, rate_limit, value)
+                .await;
+
+            None
+        }
+        Message::LiveSubmit(LiveSubmit { event_name, form }) => {
+            let topic = liveview_local_topic(liveview_id, &event_name);
+            try_!(pubsub.broadcast(&topic, form).await, Ok);
+
+            None
+        }
+        Message::LiveKey(
+            phase,
+            LiveKey {
+                event_name,
+                key,
+                code,
+                alt,
+                ctrl,
+                shift,
+                meta,
+                rate_limit,
+            },
+        ) => {
+            broadcast_rate_limited(
+                pubsub,
+                state,
+                liveview_id,
+                &event_name,
+                rate_limit,
+                LiveKeyPayload {
+                    phase,
+                    key,
+                    code,
+                    alt,
+                    ctrl,
+                    shift,
+                    meta,
+                },
+            )
+            .await;
+
+            None
+        }
+        Message::LiveFocus(LiveFocusOrBlur { event_name }) => {
+            let topic = liveview_local_topic(liveview_id, &event_name);
+            try_!(pubsub.broadcast(&topic, ()).await, Ok);
+
+            None
+        }
+        Message::LiveBlur(LiveFocusOrBlur { event_name }) => {
             let topic = liveview_local_topic(liveview_id, &event_name);
-            try_!(pubsub.broadcast(&topic, value).await, Ok);
+            try_!(pubsub.broadcast(&topic, ()).await, Ok);
+
+            None
+        }
+        Message::LiveChange(LiveInput {
+            event_name,
+            value,
+            rate_limit,
+        }) => {
+            broadcast_rate_limited(pubsub, state, liveview_id, &event_name, rate_limit, value)
+                .await;
 
             None
         }
     }
-}
\ No newline at end of file
+}
+
+/// Distinguishes `axum/live-keydown` from `axum/live-keyup`, which otherwise decode to and
+/// broadcast the same [`LiveKeyPayload`] shape.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum KeyPhase {
+    Down,
+    Up,
+}
+
+/// The payload broadcast for `axum/live-keydown`/`axum/live-keyup` events.
+#[derive(Debug, Clone, serde::Serialize)]
+struct LiveKeyPayload {
+    phase: KeyPhase,
+    key: String,
+    code: String,
+    alt: bool,
+    ctrl: bool,
+    shift: bool,
+    meta: bool,
+}
+
+/// Broadcasts `data` on `liveview_id`'s `event_name` topic, honoring a debounce/throttle hint.
+///
+/// Throttled events are either sent immediately or dropped. Debounced events are broadcast from
+/// a spawned task after the debounce window, unless a later event for the same binding supersedes
+/// it first.
+async fn broadcast_rate_limited<P, T>(
+    pubsub: &P,
+    state: &mut SocketState,
+    liveview_id: Uuid,
+    event_name: &str,
+    rate_limit: Option<RateLimit>,
+    data: T,
+) where
+    P: PubSub + Clone + Send + Sync + 'static,
+    T: serde::Serialize + Send + 'static,
+{
+    let topic = liveview_local_topic(liveview_id, event_name);
+
+    match state
+        .rate_limiter
+        .check(liveview_id, event_name, rate_limit)
+    {
+        Decision::Now => {
+            if let Err(err) = pubsub.broadcast(&topic, data).await {
+                tracing::error!(%err, "failed to broadcast event");
+            }
+        }
+        Decision::Drop => {}
+        Decision::Debounced { after, token } => {
+            let pubsub = pubsub.clone();
+            let handle = tokio::spawn(async move {
+                tokio::time::sleep(after).await;
+                if !token.is_current() {
+                    return;
+                }
+                if let Err(err) = pubsub.broadcast(&topic, data).await {
+                    tracing::error!(%err, "failed to broadcast debounced event");
+                }
+            });
+            if let Some(previous) = state
+                .debounce_tasks
+                .insert((liveview_id, event_name.to_owned()), handle)
+            {
+                previous.abort();
+            }
+        }
+    }
+}